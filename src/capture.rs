@@ -0,0 +1,60 @@
+//! Persists every received webhook as a structured JSON record under
+//! `--capture-dir`, so it can later be resent with `echo replay`.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// One captured request, written as (and read back from) a single JSONL line.
+#[derive(Serialize, Deserialize)]
+pub struct CaptureRecord {
+    pub timestamp: String,
+    pub method: String,
+    pub path: String,
+    /// Header name/value pairs, one entry per value, in the order the request's
+    /// `HeaderMap` yields them (all values of a name together). A `Vec` rather than a map, since
+    /// a request can carry the same header name more than once (e.g. repeated
+    /// `Set-Cookie`) and a map would silently collapse those to one value.
+    pub headers: Vec<(String, String)>,
+    pub body_base64: String,
+    pub provider: Option<String>,
+    pub expected: Option<String>,
+    pub received: Option<String>,
+    pub pass: Option<bool>,
+}
+
+/// Appends `record` as one line to `<dir>/captures.jsonl`, creating the
+/// directory and file as needed.
+pub fn append(dir: &Path, record: &CaptureRecord) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("captures.jsonl"))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_header_names_survive_a_json_round_trip() {
+        let record = CaptureRecord {
+            timestamp: "2025-01-01 00:00:00".to_string(),
+            method: "POST".to_string(),
+            path: "/".to_string(),
+            headers: vec![("set-cookie".to_string(), "a=1".to_string()), ("set-cookie".to_string(), "b=2".to_string())],
+            body_base64: String::new(),
+            provider: None,
+            expected: None,
+            received: None,
+            pass: None,
+        };
+
+        let json = serde_json::to_string(&record).unwrap();
+        let round_tripped: CaptureRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.headers, record.headers);
+    }
+}
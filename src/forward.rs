@@ -0,0 +1,127 @@
+//! Relays a received webhook to a downstream endpoint, turning the tool into
+//! an inline debugging proxy instead of a terminal sink.
+
+use axum::http::{HeaderMap, Method};
+use std::time::Duration;
+
+/// Hop-by-hop headers that must not be forwarded verbatim; reqwest manages
+/// framing and connection headers for the downstream request on its own.
+pub(crate) const HOP_BY_HOP: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "content-length",
+];
+
+/// Maximum number of send attempts before giving up on a forward.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// When a received request should be relayed downstream.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ForwardOn {
+    /// Forward regardless of signature verification outcome.
+    Always,
+    /// Only forward requests that passed verification.
+    PassOnly,
+}
+
+/// Outcome of a (possibly retried) forward attempt.
+pub enum ForwardOutcome {
+    Sent { status: u16, attempts: u32 },
+    Failed { attempts: u32 },
+}
+
+/// Builds the downstream header set from an inbound request: hop-by-hop
+/// headers dropped, everything else carried over with repeated names kept
+/// as repeated values (not collapsed to the last one).
+fn build_forward_headers(headers: &HeaderMap) -> reqwest::header::HeaderMap {
+    let mut forward_headers = reqwest::header::HeaderMap::new();
+    for (name, value) in headers.iter() {
+        if HOP_BY_HOP.contains(&name.as_str()) {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_str().as_bytes()),
+            reqwest::header::HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            // `insert` would replace all prior values for this name; `append`
+            // adds another value so a header repeated on the wire (e.g. two
+            // `Set-Cookie` lines) survives the forward intact.
+            forward_headers.append(name, value);
+        }
+    }
+    forward_headers
+}
+
+/// Re-emits `body` to `target_url` with the original method and headers
+/// (minus hop-by-hop ones), retrying on 5xx responses and connection errors
+/// with exponential backoff.
+pub async fn forward(
+    client: &reqwest::Client,
+    target_url: &str,
+    method: &Method,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> ForwardOutcome {
+    let reqwest_method = reqwest::Method::from_bytes(method.as_str().as_bytes()).unwrap_or(reqwest::Method::POST);
+    let forward_headers = build_forward_headers(headers);
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = client
+            .request(reqwest_method.clone(), target_url)
+            .headers(forward_headers.clone())
+            .body(body.to_vec())
+            .send()
+            .await;
+
+        match result {
+            Ok(resp) if !resp.status().is_server_error() || attempt == MAX_ATTEMPTS => {
+                return ForwardOutcome::Sent { status: resp.status().as_u16(), attempts: attempt };
+            }
+            Err(_) if attempt == MAX_ATTEMPTS => {
+                return ForwardOutcome::Failed { attempts: attempt };
+            }
+            _ => {
+                let backoff = Duration::from_millis(100 * 2u64.pow(attempt - 1));
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+
+    ForwardOutcome::Failed { attempts: MAX_ATTEMPTS }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_headers_round_trip_without_collapsing() {
+        let mut headers = HeaderMap::new();
+        headers.append("set-cookie", "a=1".parse().unwrap());
+        headers.append("set-cookie", "b=2".parse().unwrap());
+
+        let forwarded = build_forward_headers(&headers);
+        let values: Vec<&str> = forwarded.get_all("set-cookie").iter().map(|v| v.to_str().unwrap()).collect();
+        assert_eq!(values, vec!["a=1", "b=2"]);
+    }
+
+    #[test]
+    fn hop_by_hop_headers_are_dropped() {
+        let mut headers = HeaderMap::new();
+        headers.insert("host", "example.com".parse().unwrap());
+        headers.insert("connection", "keep-alive".parse().unwrap());
+        headers.insert("x-custom", "keep-me".parse().unwrap());
+
+        let forwarded = build_forward_headers(&headers);
+        assert!(!forwarded.contains_key("host"));
+        assert!(!forwarded.contains_key("connection"));
+        assert_eq!(forwarded.get("x-custom").unwrap(), "keep-me");
+    }
+}
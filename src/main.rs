@@ -4,15 +4,21 @@ use axum::{
     http::{HeaderMap, Method, StatusCode, Uri},
     Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use clap::Parser;
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use chrono::Local;
 
-// Type alias for HMAC-SHA256 encryption algorithm
-type HmacSha256 = Hmac<Sha256>;
+mod capture;
+mod forward;
+mod replay;
+mod signature;
+mod sigv4;
+use forward::ForwardOn;
+use replay::ReplayArgs;
+use signature::SignatureScheme;
 
 // --- ANSI Color Constants ---
 const RESET: &str = "\x1b[0m";
@@ -29,6 +35,9 @@ const KEY_WIDTH: usize = 18;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Verification secret key (default: sk_prod_123456)
     #[arg(short, long, default_value = "sk_prod_123456")]
     secret: String,
@@ -36,12 +45,67 @@ struct Args {
     /// Server listening port (default: 3000)
     #[arg(short, long, default_value_t = 3000)]
     port: u16,
+
+    /// Force a specific signature scheme instead of auto-detecting from
+    /// headers (one of: super, github, stripe, slack, bridge)
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// Maximum allowed age, in seconds, of a signed timestamp before the
+    /// request is rejected as a possible replay (default: 300)
+    #[arg(long, default_value_t = 300)]
+    tolerance_secs: i64,
+
+    /// PEM certificate chain for TLS termination. Requires --tls-key; when
+    /// neither is set the server listens over plain HTTP
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for TLS termination. Requires --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Downstream URL to relay received requests to (see --forward-on). When unset, the tool
+    /// only logs requests and never forwards them
+    #[arg(long)]
+    forward_url: Option<String>,
+
+    /// Whether to forward regardless of verification outcome, or only
+    /// requests that passed (default: always)
+    #[arg(long, value_enum, default_value_t = ForwardOn::Always)]
+    forward_on: ForwardOn,
+
+    /// Persist every received request as a structured JSON record (one line
+    /// per request) under this directory, alongside the usual colored output
+    #[arg(long)]
+    capture_dir: Option<PathBuf>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Re-send a request captured by --capture-dir to a downstream URL
+    Replay(ReplayArgs),
+}
+
+/// The subset of a verification outcome worth persisting to `--capture-dir`.
+#[derive(Default)]
+struct CaptureMeta {
+    provider: Option<String>,
+    expected: Option<String>,
+    received: Option<String>,
+    pass: Option<bool>,
 }
 
 // Application global state for concurrent safe sharing
 #[derive(Clone)]
 struct AppState {
     secret: String,
+    provider: Option<String>,
+    tolerance_secs: i64,
+    http_client: reqwest::Client,
+    forward_url: Option<String>,
+    forward_on: ForwardOn,
+    capture_dir: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -52,20 +116,57 @@ async fn main() {
     // Parse command line arguments
     let args = Args::parse();
 
+    if let Some(Command::Replay(replay_args)) = args.command {
+        if let Err(err) = replay::run(replay_args).await {
+            eprintln!("replay failed: {:?}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // An unrecognized `--provider` must not be allowed to silently fall back
+    // to "no header detected" at request time (see webhook_handler) — that
+    // would make a typo in this flag indistinguishable from "verification
+    // passed because there was nothing to check," which defeats the entire
+    // point of the tool. Fail loudly at startup instead.
+    if let Some(name) = &args.provider {
+        let known: Vec<&'static str> = signature::registry().iter().map(|s| s.name()).collect();
+        if !known.contains(&name.as_str()) {
+            eprintln!("error: unknown --provider {:?} (expected one of: {})", name, known.join(", "));
+            std::process::exit(1);
+        }
+    }
+
     // Helper macro for aligned printing on startup
     let print_startup = |key: &str, val: String| {
         println!("  {:<w$} : {}{}{}", key, YELLOW, val, RESET, w = KEY_WIDTH);
     };
 
+    let scheme = if args.tls_cert.is_some() { "https" } else { "http" };
+
     println!("");
     println!(" WEBHOOK RECEIVER ONLINE");
     print_startup("Secret Key", args.secret.clone());
     print_startup("Listen Port", args.port.to_string());
+    print_startup("Scheme", scheme.to_string());
+    print_startup("Provider", args.provider.clone().unwrap_or_else(|| "auto-detect".to_string()));
+    print_startup("Tolerance", format!("{}s", args.tolerance_secs));
+    print_startup("Forward To", args.forward_url.clone().unwrap_or_else(|| "disabled".to_string()));
+    print_startup(
+        "Capture Dir",
+        args.capture_dir.clone().map(|p| p.display().to_string()).unwrap_or_else(|| "disabled".to_string()),
+    );
     println!("");
 
     // Wrap state with Arc for thread-safe sharing
     let state = Arc::new(AppState {
         secret: args.secret.clone(),
+        provider: args.provider.clone(),
+        tolerance_secs: args.tolerance_secs,
+        http_client: reqwest::Client::new(),
+        forward_url: args.forward_url.clone(),
+        forward_on: args.forward_on,
+        capture_dir: args.capture_dir.clone(),
     });
 
     // Build axum router with fallback to accept any path
@@ -75,9 +176,36 @@ async fn main() {
 
     let addr = SocketAddr::from(([0, 0, 0, 0], args.port));
 
-    // Start TCP listener
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // When a cert/key pair is supplied, terminate TLS ourselves via rustls
+    // instead of requiring users to front the tool with a reverse proxy.
+    match (args.tls_cert, args.tls_key) {
+        (Some(cert), Some(key)) => {
+            let config = RustlsConfig::from_pem_file(cert, key)
+                .await
+                .expect("failed to load TLS certificate/key");
+            axum_server::bind_rustls(addr, config)
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        }
+        _ => {
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            axum::serve(listener, app).await.unwrap();
+        }
+    }
+}
+
+/// Whether a signed timestamp `ts` falls within `tolerance_secs` of `now`.
+///
+/// `ts` is attacker-controlled and unauthenticated at this point (the MAC
+/// check may still fail), so the age is computed with `abs_diff` rather than
+/// `now - ts` — a value like `i64::MIN` would overflow a plain subtraction
+/// before we ever get to compare it against the window. A negative
+/// `tolerance_secs` (not reachable via the CLI's `default_value_t`, but not
+/// ruled out by its type) never matches, since there is no sensible window.
+fn is_within_tolerance(now: i64, ts: i64, tolerance_secs: i64) -> bool {
+    let age = now.abs_diff(ts);
+    tolerance_secs >= 0 && age <= tolerance_secs as u64
 }
 
 /// Unified webhook request handler
@@ -145,61 +273,234 @@ async fn webhook_handler(
     }
     println!("");
 
-    // 3. Payload (With Auto JSON Formatting)
-    println!(" PAYLOAD");
-    if body.is_empty() {
-        log_kv("Body", "<Empty>", GRAY);
+    // An AWS SigV4 chunked-streaming body needs to be dechunked (and each
+    // chunk's signature checked) before there's a payload worth pretty-printing,
+    // so it gets its own path through sections 3 and 4.
+    let is_streaming_sigv4 = headers
+        .get("x-amz-content-sha256")
+        .and_then(|v| v.to_str().ok())
+        == Some("STREAMING-AWS4-HMAC-SHA256-PAYLOAD");
+
+    let mut capture_meta = CaptureMeta::default();
+
+    let result_code = if is_streaming_sigv4 {
+        let (code, meta) = verify_streaming_request(&state, &headers, &body, &log_kv, &log_multiline);
+        capture_meta = meta;
+        code
     } else {
-        // Try to parse the raw body bytes as JSON
-        let display_text = match serde_json::from_slice::<serde_json::Value>(&body) {
-            // If valid JSON, verify if we can pretty-print it
-            Ok(json_val) => serde_json::to_string_pretty(&json_val)
-                .unwrap_or_else(|_| String::from_utf8_lossy(&body).to_string()),
-            // If not JSON, print as raw string
-            Err(_) => String::from_utf8_lossy(&body).to_string(),
-        };
+        // 3. Payload (With Auto JSON Formatting)
+        println!(" PAYLOAD");
+        if body.is_empty() {
+            log_kv("Body", "<Empty>", GRAY);
+        } else {
+            // Try to parse the raw body bytes as JSON
+            let display_text = match serde_json::from_slice::<serde_json::Value>(&body) {
+                // If valid JSON, verify if we can pretty-print it
+                Ok(json_val) => serde_json::to_string_pretty(&json_val)
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&body).to_string()),
+                // If not JSON, print as raw string
+                Err(_) => String::from_utf8_lossy(&body).to_string(),
+            };
+
+            log_multiline("Body", &display_text, RESET);
+        }
+        println!("");
+
+        // 4. Verification
+        println!(" SIGNATURE VERIFICATION");
+
+        let schemes = signature::registry();
+        let selected = signature::select(&schemes, state.provider.as_deref(), &headers);
+
+        if let Some(scheme) = selected {
+            log_kv("Provider", scheme.name(), BLUE);
+            capture_meta.provider = Some(scheme.name().to_string());
+
+            match scheme.verify(state.secret.as_bytes(), &headers, &body) {
+                Ok(detail) => {
+                    log_kv("Secret Used", &detail.secret_used, GRAY);
+                    log_kv("Expected", &detail.expected, GRAY);
+                    log_kv("Received", &detail.received, GRAY);
+                    capture_meta.expected = Some(detail.expected.clone());
+                    capture_meta.received = Some(detail.received.clone());
+
+                    // Schemes that fold a signed timestamp into their MAC (Stripe,
+                    // Slack) let us reject captured-and-resent payloads even when
+                    // the signature itself still checks out.
+                    let stale = detail.timestamp.map(|ts| {
+                        let now = Local::now().timestamp();
+                        let age = now.abs_diff(ts);
+                        let within_window = is_within_tolerance(now, ts, state.tolerance_secs);
+                        log_kv("Age", &format!("{}s", age), if within_window { GREEN } else { RED });
+                        !within_window
+                    }).unwrap_or(false);
+
+                    capture_meta.pass = Some(detail.pass && !stale);
+
+                    if !detail.pass {
+                        log_kv("Result", "FAIL (Mismatch)", RED);
+                        StatusCode::UNAUTHORIZED
+                    } else if stale {
+                        log_kv("Result", "FAIL (Stale Timestamp)", RED);
+                        StatusCode::UNAUTHORIZED
+                    } else {
+                        log_kv("Result", "PASS", GREEN);
+                        StatusCode::OK
+                    }
+                }
+                Err(_) => {
+                    capture_meta.pass = Some(false);
+                    log_kv("Result", "FAIL (Format Error)", RED);
+                    StatusCode::BAD_REQUEST
+                }
+            }
+        } else {
+            log_kv("Result", "SKIPPED (No Header)", YELLOW);
+            StatusCode::OK
+        }
+    };
 
-        log_multiline("Body", &display_text, RESET);
+    // 5. Forwarding (Optional)
+    if let Some(target) = &state.forward_url {
+        // A request with no signature header is answered with 200 but was
+        // never verified, so pass-only keys off the recorded outcome instead.
+        let should_forward = matches!(state.forward_on, ForwardOn::Always) || capture_meta.pass == Some(true);
+
+        if should_forward {
+            match forward::forward(&state.http_client, target, &method, &headers, &body).await {
+                forward::ForwardOutcome::Sent { status, attempts } => {
+                    log_kv("Forwarded", &format!("{} (attempt {})", status, attempts), GREEN);
+                }
+                forward::ForwardOutcome::Failed { attempts } => {
+                    log_kv("Forwarded", &format!("FAILED after {} attempts", attempts), RED);
+                }
+            }
+        } else {
+            log_kv("Forwarded", "SKIPPED (not verified)", YELLOW);
+        }
     }
-    println!("");
 
-    // 4. Verification
-    println!(" SIGNATURE VERIFICATION");
+    // 6. Capture (Optional)
+    if let Some(dir) = &state.capture_dir {
+        let record = capture::CaptureRecord {
+            timestamp: timestamp.clone(),
+            method: method.to_string(),
+            path: uri.path().to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.as_str().to_string(), value.to_str().unwrap_or("<binary>").to_string()))
+                .collect(),
+            body_base64: base64::encode(&body),
+            provider: capture_meta.provider,
+            expected: capture_meta.expected,
+            received: capture_meta.received,
+            pass: capture_meta.pass,
+        };
 
-    let result_code = if let Some(signature_header) = headers.get("X-Super-Signature") {
-        let signature_str = signature_header.to_str().unwrap_or("");
+        if let Err(err) = capture::append(dir, &record) {
+            log_kv("Capture", &format!("FAILED ({})", err), RED);
+        }
+    }
 
-        // Expected format: algo=hash (e.g., sha256=abcdef...)
-        if let Some((_, provided_sign)) = signature_str.split_once('=') {
-            // Initialize HMAC-SHA256 with the secret from State (CLI args)
-            let mut mac = HmacSha256::new_from_slice(state.secret.as_bytes())
-                .expect("HMAC init failed");
+    println!("==================================================\n");
 
-            // IMPORTANT: Verify against the raw `body` bytes, NOT the pretty-printed string
-            mac.update(&body);
-            let expected_sign = hex::encode(mac.finalize().into_bytes());
+    result_code
+}
 
-            log_kv("Secret Used", &state.secret, GRAY);
-            log_kv("Expected", &expected_sign, GRAY);
-            log_kv("Received", provided_sign, GRAY);
+/// Dechunks and verifies a `STREAMING-AWS4-HMAC-SHA256-PAYLOAD` body, printing
+/// the reassembled payload and a per-chunk verification trail in place of the
+/// usual sections 3 and 4.
+fn verify_streaming_request(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: &[u8],
+    log_kv: &dyn Fn(&str, &str, &str),
+    log_multiline: &dyn Fn(&str, &str, &str),
+) -> (StatusCode, CaptureMeta) {
+    println!(" PAYLOAD (aws-chunked)");
+
+    let parsed = headers
+        .get("x-amz-date")
+        .and_then(|v| v.to_str().ok())
+        .zip(headers.get("Authorization").and_then(|v| v.to_str().ok()).and_then(sigv4::parse_authorization))
+        .map(|(date, (date_stamp, region, service, scope, seed_signature))| {
+            let signing_key = sigv4::derive_signing_key(&state.secret, &date_stamp, &region, &service);
+            sigv4::verify_streaming_body(&signing_key, date, &scope, &seed_signature, body)
+        });
+
+    match parsed {
+        Some(Ok(streaming)) => {
+            let display_text = match serde_json::from_slice::<serde_json::Value>(&streaming.body) {
+                Ok(json_val) => serde_json::to_string_pretty(&json_val)
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&streaming.body).to_string()),
+                Err(_) => String::from_utf8_lossy(&streaming.body).to_string(),
+            };
+            log_multiline("Body", &display_text, RESET);
+            println!("");
+
+            println!(" SIGNATURE VERIFICATION (SigV4 streaming)");
+            for chunk in &streaming.chunks {
+                let key = format!("Chunk {}", chunk.index);
+                let val = format!("{} bytes, sig {}", chunk.data_len, chunk.signature);
+                log_kv(&key, &val, if chunk.valid { GREEN } else { RED });
+            }
 
-            if provided_sign == expected_sign {
+            let pass = streaming.all_valid();
+            let code = if pass {
                 log_kv("Result", "PASS", GREEN);
                 StatusCode::OK
             } else {
-                log_kv("Result", "FAIL (Mismatch)", RED);
+                log_kv("Result", "FAIL (Chunk Mismatch)", RED);
                 StatusCode::UNAUTHORIZED
-            }
-        } else {
+            };
+
+            (code, CaptureMeta { provider: Some("sigv4-stream".to_string()), pass: Some(pass), ..Default::default() })
+        }
+        Some(Err(_)) => {
+            log_kv("Body", "<Malformed chunked stream>", RED);
+            println!("");
+            println!(" SIGNATURE VERIFICATION (SigV4 streaming)");
             log_kv("Result", "FAIL (Format Error)", RED);
-            StatusCode::BAD_REQUEST
+            (StatusCode::BAD_REQUEST, CaptureMeta { provider: Some("sigv4-stream".to_string()), pass: Some(false), ..Default::default() })
         }
-    } else {
-        log_kv("Result", "SKIPPED (No Header)", YELLOW);
-        StatusCode::OK
-    };
+        None => {
+            log_kv("Body", "<Unparseable: missing x-amz-date/Authorization>", RED);
+            println!("");
+            println!(" SIGNATURE VERIFICATION (SigV4 streaming)");
+            log_kv("Result", "FAIL (Format Error)", RED);
+            (StatusCode::BAD_REQUEST, CaptureMeta { provider: Some("sigv4-stream".to_string()), pass: Some(false), ..Default::default() })
+        }
+    }
+}
 
-    println!("==================================================\n");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    result_code
+    #[test]
+    fn within_tolerance_accepts_recent_timestamp() {
+        assert!(is_within_tolerance(1_000_000, 999_800, 300));
+    }
+
+    #[test]
+    fn within_tolerance_rejects_old_timestamp() {
+        assert!(!is_within_tolerance(1_000_000, 999_000, 300));
+    }
+
+    #[test]
+    fn within_tolerance_handles_i64_min_without_overflow() {
+        // A plain `now - ts` would overflow i64 here; `is_within_tolerance`
+        // must neither panic nor wrap, and must reject the timestamp.
+        assert!(!is_within_tolerance(1_000_000, i64::MIN, 300));
+    }
+
+    #[test]
+    fn within_tolerance_handles_i64_max_without_overflow() {
+        assert!(!is_within_tolerance(i64::MIN, i64::MAX, 300));
+    }
+
+    #[test]
+    fn negative_tolerance_never_matches() {
+        assert!(!is_within_tolerance(1_000_000, 1_000_000, -1));
+    }
 }
@@ -0,0 +1,150 @@
+//! `echo replay` — resend a captured webhook record, optionally recomputing
+//! its signature with a fresh secret so it passes downstream verification.
+
+use crate::capture::CaptureRecord;
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::path::PathBuf;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+    /// Path to a captured record written by --capture-dir (a single JSON
+    /// object, or the first line of a captures.jsonl file)
+    pub file: PathBuf,
+
+    /// Downstream URL to resend the captured request to
+    #[arg(long)]
+    pub target: String,
+
+    /// Recompute the signature with this secret before resending, instead
+    /// of replaying the captured signature header verbatim
+    #[arg(long)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Http(reqwest::Error),
+}
+
+/// Reads the captured record at `args.file` and re-sends it to `args.target`.
+pub async fn run(args: ReplayArgs) -> Result<(), ReplayError> {
+    let contents = std::fs::read_to_string(&args.file).map_err(ReplayError::Io)?;
+    let line = contents.lines().find(|l| !l.trim().is_empty()).unwrap_or(contents.trim());
+    let record: CaptureRecord = serde_json::from_str(line).map_err(ReplayError::Parse)?;
+
+    let body = base64::decode(&record.body_base64).unwrap_or_default();
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in &record.headers {
+        if crate::forward::HOP_BY_HOP.contains(&name.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+            reqwest::header::HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            // `insert` would collapse a header that was captured with more
+            // than one value (e.g. two `Set-Cookie` lines) down to the last
+            // one; `append` keeps every captured value on the wire.
+            headers.append(name, value);
+        }
+    }
+
+    if let Some(secret) = &args.secret {
+        match recompute_signature(record.provider.as_deref(), secret, &body) {
+            Some(updates) => {
+                for (header_name, header_value) in updates {
+                    if let (Ok(name), Ok(value)) = (
+                        reqwest::header::HeaderName::from_bytes(header_name.as_bytes()),
+                        reqwest::header::HeaderValue::from_str(&header_value),
+                    ) {
+                        // Unlike the captured headers above, a recomputed
+                        // signature header replaces rather than joins its
+                        // captured counterpart: `remove` drops every
+                        // captured value for this name (stale signature or
+                        // timestamp) before `append` adds the fresh one, so
+                        // the request doesn't carry both old and new values.
+                        headers.remove(&name);
+                        headers.append(name, value);
+                    }
+                }
+            }
+            None => eprintln!(
+                "warning: cannot recompute a signature for provider {:?}; replaying the captured header verbatim",
+                record.provider
+            ),
+        }
+    }
+
+    let method = reqwest::Method::from_bytes(record.method.as_bytes()).unwrap_or(reqwest::Method::POST);
+
+    let client = reqwest::Client::new();
+    let response = client
+        .request(method, &args.target)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .map_err(ReplayError::Http)?;
+
+    println!("Replayed {} -> {} ({})", record.path, args.target, response.status());
+    Ok(())
+}
+
+/// Computes an HMAC-SHA256 tag over `message` with `secret`.
+fn hmac_tag(secret: &str, message: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Rebuilds the signature header(s) for a provider, returning the
+/// `(header name, header value)` pairs to overwrite on the replayed request.
+///
+/// Stripe and Slack fold a timestamp into their signed string and are
+/// rejected as stale outside the tolerance window (see the replay-protection
+/// check in `webhook_handler`), so a captured signature is only valid for a
+/// few minutes after capture — recomputing means re-signing with the
+/// current time, not replaying the original `t=`/`X-Slack-Request-Timestamp`.
+/// The SigV4 chunk-signature chain isn't recomputed here: it signs a whole
+/// sequence of chunks, not a single header, so there is no one value to swap in.
+fn recompute_signature(provider: Option<&str>, secret: &str, body: &[u8]) -> Option<Vec<(String, String)>> {
+    match provider {
+        Some("super") => {
+            let tag = hmac_tag(secret, body);
+            Some(vec![("X-Super-Signature".to_string(), format!("sha256={}", hex::encode(tag)))])
+        }
+        Some("github") => {
+            let tag = hmac_tag(secret, body);
+            Some(vec![("X-Hub-Signature-256".to_string(), format!("sha256={}", hex::encode(tag)))])
+        }
+        Some("bridge") => {
+            let tag = hmac_tag(secret, body);
+            Some(vec![("X-Bridge-Signature".to_string(), format!("sha256={}", base64::encode(tag)))])
+        }
+        Some("stripe") => {
+            let t = Local::now().timestamp();
+            let mut message = format!("{t}.").into_bytes();
+            message.extend_from_slice(body);
+            let tag = hmac_tag(secret, &message);
+            Some(vec![("Stripe-Signature".to_string(), format!("t={t},v1={}", hex::encode(tag)))])
+        }
+        Some("slack") => {
+            let t = Local::now().timestamp();
+            let mut message = format!("v0:{t}:").into_bytes();
+            message.extend_from_slice(body);
+            let tag = hmac_tag(secret, &message);
+            Some(vec![
+                ("X-Slack-Request-Timestamp".to_string(), t.to_string()),
+                ("X-Slack-Signature".to_string(), format!("v0={}", hex::encode(tag))),
+            ])
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,387 @@
+//! Pluggable webhook signature verification schemes.
+//!
+//! Each real-world provider signs requests a little differently: the header
+//! it uses, whether a timestamp is folded into the signed string, and the
+//! encoding of the resulting MAC (hex vs base64). [`SignatureScheme`] models
+//! that variation so `webhook_handler` can stay provider-agnostic and just
+//! ask the [`registry`] which scheme applies to a given request.
+
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Why a scheme could not produce a [`VerificationDetail`].
+#[derive(Debug)]
+pub enum VerifyError {
+    /// A header required by this scheme was missing.
+    MissingHeader,
+    /// A header was present but did not match the expected `key=value` shape.
+    FormatError,
+}
+
+/// Everything the caller needs to render the "SIGNATURE VERIFICATION" block
+/// for a single scheme.
+pub struct VerificationDetail {
+    pub secret_used: String,
+    pub expected: String,
+    pub received: String,
+    pub pass: bool,
+    /// Unix timestamp the scheme folded into its signed string, if any.
+    /// `None` for schemes with no signed timestamp (e.g. GitHub, the legacy
+    /// `X-Super-Signature`), which have no replay window to check.
+    pub timestamp: Option<i64>,
+}
+
+/// A single provider's signing convention: which header(s) it uses, how to
+/// build the canonical string that gets signed, and how the MAC is encoded.
+pub trait SignatureScheme: Send + Sync {
+    /// Stable identifier, also accepted by `--provider`.
+    fn name(&self) -> &'static str;
+
+    /// Whether this scheme's header(s) are present on the request, used for
+    /// auto-detection when `--provider` is not given.
+    fn detect(&self, headers: &HeaderMap) -> bool;
+
+    /// Verify `body` against `secret` using this scheme's convention.
+    fn verify(&self, secret: &[u8], headers: &HeaderMap, body: &[u8]) -> Result<VerificationDetail, VerifyError>;
+}
+
+/// Computes an HMAC-SHA256 over `message` and returns both the raw tag and
+/// its hex encoding, since most schemes want to log the hex form regardless
+/// of which encoding the provider actually sends.
+fn hmac_sha256(secret: &[u8], message: &[u8]) -> (Vec<u8>, String) {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    let tag = mac.finalize().into_bytes().to_vec();
+    let hex = hex::encode(&tag);
+    (tag, hex)
+}
+
+/// Recomputes the MAC over `message` and compares it against `provided` via
+/// `Mac::verify_slice`, which runs in constant time regardless of where the
+/// bytes first differ. This replaces the old `provided_sign == expected_sign`
+/// string comparison, which short-circuits on the first mismatched byte and
+/// leaks timing information about how much of the signature an attacker got
+/// right.
+fn verify_mac(secret: &[u8], message: &[u8], provided: &[u8]) -> bool {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.verify_slice(provided).is_ok()
+}
+
+/// Legacy scheme this tool shipped with: `X-Super-Signature: algo=<hex>`,
+/// HMAC-SHA256 over the raw body.
+pub struct SuperScheme;
+
+impl SignatureScheme for SuperScheme {
+    fn name(&self) -> &'static str {
+        "super"
+    }
+
+    fn detect(&self, headers: &HeaderMap) -> bool {
+        headers.contains_key("X-Super-Signature")
+    }
+
+    fn verify(&self, secret: &[u8], headers: &HeaderMap, body: &[u8]) -> Result<VerificationDetail, VerifyError> {
+        let raw = headers
+            .get("X-Super-Signature")
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .unwrap_or("");
+
+        let (_, received) = raw.split_once('=').ok_or(VerifyError::FormatError)?;
+        let (_, expected) = hmac_sha256(secret, body);
+        let provided_bytes = hex::decode(received).unwrap_or_default();
+
+        Ok(VerificationDetail {
+            secret_used: String::from_utf8_lossy(secret).to_string(),
+            expected,
+            received: received.to_string(),
+            pass: verify_mac(secret, body, &provided_bytes),
+            timestamp: None,
+        })
+    }
+}
+
+/// GitHub: `X-Hub-Signature-256: sha256=<hex>`, HMAC-SHA256 over the raw body.
+pub struct GitHubScheme;
+
+impl SignatureScheme for GitHubScheme {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn detect(&self, headers: &HeaderMap) -> bool {
+        headers.contains_key("X-Hub-Signature-256")
+    }
+
+    fn verify(&self, secret: &[u8], headers: &HeaderMap, body: &[u8]) -> Result<VerificationDetail, VerifyError> {
+        let raw = headers
+            .get("X-Hub-Signature-256")
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .unwrap_or("");
+
+        let (_, received) = raw.split_once('=').ok_or(VerifyError::FormatError)?;
+        let (_, expected) = hmac_sha256(secret, body);
+        let provided_bytes = hex::decode(received).unwrap_or_default();
+
+        Ok(VerificationDetail {
+            secret_used: String::from_utf8_lossy(secret).to_string(),
+            expected,
+            received: received.to_string(),
+            pass: verify_mac(secret, body, &provided_bytes),
+            timestamp: None,
+        })
+    }
+}
+
+/// Stripe: `Stripe-Signature: t=<ts>,v1=<hex>`, HMAC-SHA256 over `"{t}.{body}"`.
+pub struct StripeScheme;
+
+impl StripeScheme {
+    /// Pulls the `t=` and `v1=` fields out of Stripe's comma-separated header.
+    fn parse(raw: &str) -> Option<(&str, &str)> {
+        let mut timestamp = None;
+        let mut signature = None;
+        for field in raw.split(',') {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "t" => timestamp = Some(value),
+                "v1" => signature = Some(value),
+                _ => {}
+            }
+        }
+        Some((timestamp?, signature?))
+    }
+}
+
+impl SignatureScheme for StripeScheme {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn detect(&self, headers: &HeaderMap) -> bool {
+        headers.contains_key("Stripe-Signature")
+    }
+
+    fn verify(&self, secret: &[u8], headers: &HeaderMap, body: &[u8]) -> Result<VerificationDetail, VerifyError> {
+        let raw = headers
+            .get("Stripe-Signature")
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .unwrap_or("");
+
+        let (timestamp, received) = Self::parse(raw).ok_or(VerifyError::FormatError)?;
+
+        let mut message = Vec::with_capacity(timestamp.len() + 1 + body.len());
+        message.extend_from_slice(timestamp.as_bytes());
+        message.push(b'.');
+        message.extend_from_slice(body);
+
+        let (_, expected) = hmac_sha256(secret, &message);
+        let provided_bytes = hex::decode(received).unwrap_or_default();
+
+        Ok(VerificationDetail {
+            secret_used: String::from_utf8_lossy(secret).to_string(),
+            expected,
+            received: received.to_string(),
+            pass: verify_mac(secret, &message, &provided_bytes),
+            timestamp: timestamp.parse().ok(),
+        })
+    }
+}
+
+/// Slack: `X-Slack-Signature: v0=<hex>` over `"v0:{ts}:{body}"`, with the
+/// timestamp carried separately in `X-Slack-Request-Timestamp`.
+pub struct SlackScheme;
+
+impl SignatureScheme for SlackScheme {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn detect(&self, headers: &HeaderMap) -> bool {
+        headers.contains_key("X-Slack-Signature") && headers.contains_key("X-Slack-Request-Timestamp")
+    }
+
+    fn verify(&self, secret: &[u8], headers: &HeaderMap, body: &[u8]) -> Result<VerificationDetail, VerifyError> {
+        let raw = headers
+            .get("X-Slack-Signature")
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .unwrap_or("");
+        let timestamp = headers
+            .get("X-Slack-Request-Timestamp")
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .unwrap_or("");
+
+        let (_, received) = raw.split_once('=').ok_or(VerifyError::FormatError)?;
+
+        let mut message = Vec::with_capacity(2 + 1 + timestamp.len() + 1 + body.len());
+        message.extend_from_slice(b"v0:");
+        message.extend_from_slice(timestamp.as_bytes());
+        message.push(b':');
+        message.extend_from_slice(body);
+
+        let (_, expected) = hmac_sha256(secret, &message);
+        let provided_bytes = hex::decode(received).unwrap_or_default();
+
+        Ok(VerificationDetail {
+            secret_used: String::from_utf8_lossy(secret).to_string(),
+            expected,
+            received: received.to_string(),
+            pass: verify_mac(secret, &message, &provided_bytes),
+            timestamp: timestamp.parse().ok(),
+        })
+    }
+}
+
+/// The base64-output variant used by the external `webhook_bridge`
+/// middleware: `X-Bridge-Signature: sha256=<base64>` over the raw body.
+pub struct BridgeScheme;
+
+impl SignatureScheme for BridgeScheme {
+    fn name(&self) -> &'static str {
+        "bridge"
+    }
+
+    fn detect(&self, headers: &HeaderMap) -> bool {
+        headers.contains_key("X-Bridge-Signature")
+    }
+
+    fn verify(&self, secret: &[u8], headers: &HeaderMap, body: &[u8]) -> Result<VerificationDetail, VerifyError> {
+        let raw = headers
+            .get("X-Bridge-Signature")
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .unwrap_or("");
+
+        let (_, received) = raw.split_once('=').ok_or(VerifyError::FormatError)?;
+        let (expected_bytes, _) = hmac_sha256(secret, body);
+        let expected = base64::encode(&expected_bytes);
+        let provided_bytes = base64::decode(received).unwrap_or_default();
+
+        Ok(VerificationDetail {
+            secret_used: String::from_utf8_lossy(secret).to_string(),
+            expected,
+            received: received.to_string(),
+            pass: verify_mac(secret, body, &provided_bytes),
+            timestamp: None,
+        })
+    }
+}
+
+/// All known schemes, in auto-detection priority order. Provider-specific
+/// headers are checked before the generic legacy `X-Super-Signature`, so a
+/// request carrying both is treated as coming from the named provider.
+pub fn registry() -> Vec<Box<dyn SignatureScheme>> {
+    vec![
+        Box::new(GitHubScheme),
+        Box::new(StripeScheme),
+        Box::new(SlackScheme),
+        Box::new(BridgeScheme),
+        Box::new(SuperScheme),
+    ]
+}
+
+/// Picks the scheme to use for a request: the one named by `--provider` if
+/// set, otherwise the first scheme in [`registry`] whose header(s) are present.
+pub fn select<'a>(schemes: &'a [Box<dyn SignatureScheme>], provider: Option<&str>, headers: &HeaderMap) -> Option<&'a dyn SignatureScheme> {
+    if let Some(name) = provider {
+        return schemes.iter().map(|s| s.as_ref()).find(|s| s.name() == name);
+    }
+    schemes.iter().map(|s| s.as_ref()).find(|s| s.detect(headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_honors_explicit_provider_over_headers() {
+        let schemes = registry();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Hub-Signature-256", "sha256=deadbeef".parse().unwrap());
+        let selected = select(&schemes, Some("stripe"), &headers);
+        assert_eq!(selected.unwrap().name(), "stripe");
+    }
+
+    #[test]
+    fn select_returns_none_for_unknown_explicit_provider() {
+        // `main` rejects an unknown `--provider` at startup, so in practice
+        // `select` only ever sees a name from `registry()` or `None` — but it
+        // must still fail closed (no scheme) rather than falling back to
+        // auto-detection if that invariant is ever violated.
+        let schemes = registry();
+        let headers = HeaderMap::new();
+        assert!(select(&schemes, Some("not-a-real-provider"), &headers).is_none());
+    }
+
+    #[test]
+    fn select_auto_detects_from_headers_when_no_provider_set() {
+        let schemes = registry();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Hub-Signature-256", "sha256=deadbeef".parse().unwrap());
+        let selected = select(&schemes, None, &headers);
+        assert_eq!(selected.unwrap().name(), "github");
+    }
+
+    #[test]
+    fn select_returns_none_when_nothing_detected_and_no_provider_set() {
+        let schemes = registry();
+        let headers = HeaderMap::new();
+        assert!(select(&schemes, None, &headers).is_none());
+    }
+
+    #[test]
+    fn stripe_verify_passes_with_matching_mac() {
+        let secret = b"whsec";
+        let body = b"{\"id\":1}";
+        let mut message = b"1700000000.".to_vec();
+        message.extend_from_slice(body);
+        let (_, hex_sig) = hmac_sha256(secret, &message);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Stripe-Signature", format!("t=1700000000,v1={hex_sig}").parse().unwrap());
+
+        let detail = StripeScheme.verify(secret, &headers, body).unwrap();
+        assert!(detail.pass);
+        assert_eq!(detail.timestamp, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn slack_verify_passes_with_matching_mac() {
+        let secret = b"slack-secret";
+        let body = b"payload";
+        let mut message = b"v0:1700000000:".to_vec();
+        message.extend_from_slice(body);
+        let (_, hex_sig) = hmac_sha256(secret, &message);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Slack-Request-Timestamp", "1700000000".parse().unwrap());
+        headers.insert("X-Slack-Signature", format!("v0={hex_sig}").parse().unwrap());
+
+        let detail = SlackScheme.verify(secret, &headers, body).unwrap();
+        assert!(detail.pass);
+        assert_eq!(detail.timestamp, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn slack_verify_fails_on_tampered_body() {
+        let secret = b"slack-secret";
+        let mut message = b"v0:1700000000:".to_vec();
+        message.extend_from_slice(b"original");
+        let (_, hex_sig) = hmac_sha256(secret, &message);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Slack-Request-Timestamp", "1700000000".parse().unwrap());
+        headers.insert("X-Slack-Signature", format!("v0={hex_sig}").parse().unwrap());
+
+        let detail = SlackScheme.verify(secret, &headers, b"tampered").unwrap();
+        assert!(!detail.pass);
+    }
+}